@@ -0,0 +1,175 @@
+//! Building AUR packages inside throwaway containers, so that `base-devel`
+//! and makedepends never touch the host system.
+
+use log::debug;
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::SystemTime;
+
+/// The container runtime to build with.
+#[derive(Debug, Clone, Copy)]
+pub enum Runtime {
+    /// The `docker` CLI.
+    Docker,
+    /// The `podman` CLI.
+    Podman,
+}
+
+impl Runtime {
+    fn binary(self) -> &'static str {
+        match self {
+            Runtime::Docker => "docker",
+            Runtime::Podman => "podman",
+        }
+    }
+}
+
+/// A container-build-related error.
+pub enum Error {
+    /// Some IO action failed.
+    Io(std::io::Error),
+    /// Building the container image failed.
+    Image(String),
+    /// Running `makepkg` inside the container failed.
+    Build(PathBuf),
+    /// Copying the built tarballs out of the container failed.
+    Copy(PathBuf),
+}
+
+impl From<std::io::Error> for Error {
+    fn from(v: std::io::Error) -> Self {
+        Self::Io(v)
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Image(tag) => write!(f, "Failed to build the container image: {}", tag),
+            Error::Build(p) => write!(f, "The containerized build of {} failed", p.display()),
+            Error::Copy(p) => write!(f, "Failed to copy build artifacts out of {}", p.display()),
+        }
+    }
+}
+
+/// Fill in the `{{ image }}`, `{{ pkg }}`, and `{{ flags }}` placeholders of a
+/// user-supplied Dockerfile template.
+fn render(template: &str, image: &str, pkg: &str, flags: &str) -> String {
+    template
+        .replace("{{ image }}", image)
+        .replace("{{ pkg }}", pkg)
+        .replace("{{ flags }}", flags)
+}
+
+/// Build an AUR package inside a throwaway container, so that `base-devel`
+/// and the package's makedepends never pollute the host.
+///
+/// `template` is the contents of a user-supplied Dockerfile containing the
+/// `{{ image }}`/`{{ pkg }}`/`{{ flags }}` placeholders (see [`render`]),
+/// `pkg_dir` is the already-cloned PKGBUILD directory, and `out_dir` is where
+/// the resulting `*.pkg.tar.*` artifacts are copied once the container has
+/// finished writing them to `/out`.
+///
+/// `out_dir` is typically a long-lived, shared cache rather than a scratch
+/// directory, so the paths of just the tarballs *this* build produced are
+/// returned rather than leaving callers to assume every file under `out_dir`
+/// belongs to this run.
+pub fn build(
+    runtime: Runtime,
+    template: &str,
+    image: &str,
+    pkg_dir: &Path,
+    flags: &str,
+    out_dir: &Path,
+) -> Result<Vec<PathBuf>, Error> {
+    let pkg = pkg_dir
+        .file_name()
+        .and_then(OsStr::to_str)
+        .unwrap_or("package");
+
+    let dockerfile = render(template, image, pkg, flags);
+    let context = pkg_dir.join(".aura-container");
+    fs::create_dir_all(&context)?;
+    fs::write(context.join("Dockerfile"), dockerfile)?;
+
+    let tag = format!("aura-build-{}", pkg);
+    debug!("Building container image {}", tag);
+
+    Command::new(runtime.binary())
+        .arg("build")
+        .arg("-t")
+        .arg(&tag)
+        .arg("-f")
+        .arg(context.join("Dockerfile"))
+        .arg(pkg_dir)
+        .stdout(Stdio::null())
+        .status()?
+        .success()
+        .then(|| ())
+        .ok_or_else(|| Error::Image(tag.clone()))?;
+
+    let container = format!("aura-build-{}-{}", pkg, std::process::id());
+    debug!("Running the containerized build of {}", pkg);
+
+    let ran = Command::new(runtime.binary())
+        .arg("run")
+        .arg("--name")
+        .arg(&container)
+        .arg(&tag)
+        .status()?
+        .success();
+
+    // Whether or not `makepkg` succeeded, try to recover whatever did land in
+    // `/out` before reporting the failure, then always clean up the
+    // container so repeated builds don't pile up stopped containers.
+    fs::create_dir_all(out_dir)?;
+    let before = dir_entries(out_dir)?;
+    let copy_ok = Command::new(runtime.binary())
+        .arg("cp")
+        .arg(format!("{}:/out/.", container))
+        .arg(out_dir)
+        .stderr(Stdio::null())
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false);
+    let after = dir_entries(out_dir)?;
+
+    let _ = Command::new(runtime.binary())
+        .arg("rm")
+        .arg("-f")
+        .arg(&container)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+
+    if !ran {
+        return Err(Error::Build(pkg_dir.to_path_buf()));
+    }
+    if !copy_ok {
+        return Err(Error::Copy(out_dir.to_path_buf()));
+    }
+
+    Ok(after
+        .into_iter()
+        .filter(|(path, modified)| before.get(path) != Some(modified))
+        .map(|(path, _)| path)
+        .collect())
+}
+
+/// The paths directly inside `dir` with their modification times, used to
+/// diff `out_dir` before and after a container's artifacts are copied in, so
+/// only tarballs that are new or were overwritten by this build are reported
+/// rather than everything the cache has ever accumulated.
+fn dir_entries(dir: &Path) -> Result<HashMap<PathBuf, Option<SystemTime>>, Error> {
+    Ok(fs::read_dir(dir)?
+        .filter_map(|e| e.ok())
+        .map(|e| {
+            let modified = e.metadata().and_then(|m| m.modified()).ok();
+            (e.path(), modified)
+        })
+        .collect())
+}
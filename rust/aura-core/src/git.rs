@@ -3,6 +3,12 @@
 use log::debug;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// How many repos to clone/pull at once when a caller doesn't specify, to
+/// avoid hammering the AUR with an unbounded number of connections.
+pub const DEFAULT_JOBS: usize = 8;
 
 /// A git-related error.
 pub enum Error {
@@ -69,3 +75,83 @@ pub fn pull(dir: &Path) -> Result<(), Error> {
         .then(|| ())
         .ok_or_else(|| Error::Pull(dir.to_path_buf()))
 }
+
+/// The combined outcome of a batch clone/pull: which repos made it, and
+/// which didn't along with why.
+pub struct BatchResult {
+    /// Repos that completed successfully.
+    pub succeeded: Vec<PathBuf>,
+    /// Repos that failed, paired with the error each one hit.
+    pub failed: Vec<(PathBuf, Error)>,
+}
+
+/// Shallow-clone many repositories at once, spread across a bounded pool of
+/// `jobs` worker threads so a large package set doesn't pay for each clone's
+/// network round-trip serially. One repo failing to clone doesn't stop the
+/// others.
+pub fn clone_many(repos: &[(PathBuf, PathBuf)], jobs: usize) -> BatchResult {
+    run_pool(
+        repos.to_vec(),
+        jobs,
+        |(_, target)| target.clone(),
+        |(url, target)| shallow_clone(url, target),
+    )
+}
+
+/// Pull many already-cloned repositories at once. See [`clone_many`].
+pub fn pull_many(dirs: &[PathBuf], jobs: usize) -> BatchResult {
+    run_pool(dirs.to_vec(), jobs, PathBuf::clone, |dir| pull(dir))
+}
+
+/// Run `work` over `items` across a bounded pool of `jobs` worker threads,
+/// collecting the successes and failures into a [`BatchResult`].
+///
+/// `label` extracts the path to report a result under *before* `work` runs,
+/// so a failure is always paired with the repo that actually produced it
+/// rather than whatever path happens to be embedded in the `Error` variant.
+fn run_pool<T, L, F>(items: Vec<T>, jobs: usize, label: L, work: F) -> BatchResult
+where
+    T: Send + 'static,
+    L: Fn(&T) -> PathBuf + Send + Sync + 'static,
+    F: Fn(&T) -> Result<(), Error> + Send + Sync + 'static,
+{
+    let jobs = jobs.max(1).min(items.len().max(1));
+    let label = Arc::new(label);
+    let work = Arc::new(work);
+    let queue = Arc::new(Mutex::new(items));
+    let succeeded = Arc::new(Mutex::new(Vec::new()));
+    let failed = Arc::new(Mutex::new(Vec::new()));
+
+    let handles: Vec<_> = (0..jobs)
+        .map(|_| {
+            let queue = Arc::clone(&queue);
+            let succeeded = Arc::clone(&succeeded);
+            let failed = Arc::clone(&failed);
+            let label = Arc::clone(&label);
+            let work = Arc::clone(&work);
+
+            thread::spawn(move || loop {
+                let item = queue.lock().unwrap().pop();
+                match item {
+                    None => break,
+                    Some(item) => {
+                        let path = label(&item);
+                        match work(&item) {
+                            Ok(()) => succeeded.lock().unwrap().push(path),
+                            Err(e) => failed.lock().unwrap().push((path, e)),
+                        }
+                    }
+                }
+            })
+        })
+        .collect();
+
+    for h in handles {
+        let _ = h.join();
+    }
+
+    BatchResult {
+        succeeded: Arc::try_unwrap(succeeded).unwrap().into_inner().unwrap(),
+        failed: Arc::try_unwrap(failed).unwrap().into_inner().unwrap(),
+    }
+}
@@ -6,8 +6,11 @@
 pub mod aur;
 pub mod cache;
 pub mod common;
+pub mod container;
 pub mod deps;
 pub mod git;
 pub mod log;
+pub mod pacnew;
 pub mod snapshot;
+pub mod snapshot_db;
 mod utils;
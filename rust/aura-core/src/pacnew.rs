@@ -0,0 +1,60 @@
+//! Detecting `.pacnew`/`.pacsave` files left behind by pacman transactions
+//! that touch configuration-owning packages.
+
+use std::path::{Path, PathBuf};
+
+/// A `.pacnew` or `.pacsave` file found beside the original config it was
+/// generated from.
+#[derive(Debug, Clone)]
+pub struct PacFile {
+    /// The original config file this one sits beside.
+    pub original: PathBuf,
+    /// The `.pacnew`/`.pacsave` file itself.
+    pub new: PathBuf,
+}
+
+/// Recursively scan `root` for `.pacnew` and `.pacsave` files, pairing each
+/// one with the original config file it was generated beside.
+///
+/// Directories that can't be read (most commonly due to permissions) are
+/// silently skipped rather than aborting the whole scan. Symlinked
+/// directories are never followed, so a symlink loop can't send this into an
+/// infinite recursion.
+///
+/// Callers should pass a narrow, config-owning root (e.g. `/etc`) rather
+/// than the filesystem root: pacman only ever leaves these files beside
+/// config it manages, and scanning the whole filesystem would needlessly
+/// walk `/proc`, `/sys`, network mounts, and the like.
+pub fn scan(root: &Path) -> Vec<PacFile> {
+    let mut found = Vec::new();
+    walk(root, &mut found);
+    found
+}
+
+fn walk(dir: &Path, found: &mut Vec<PacFile>) {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(e) => e,
+        Err(_) => return,
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+
+        // Use the entry's own metadata (no symlink-following) to decide
+        // whether to recurse, so a symlink cycle can't loop forever.
+        let is_real_dir = entry
+            .file_type()
+            .map(|t| t.is_dir())
+            .unwrap_or(false);
+
+        if is_real_dir {
+            walk(&path, found);
+        } else if matches!(path.extension().and_then(|e| e.to_str()), Some("pacnew" | "pacsave"))
+        {
+            found.push(PacFile {
+                original: path.with_extension(""),
+                new: path,
+            });
+        }
+    }
+}
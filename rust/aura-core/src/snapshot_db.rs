@@ -0,0 +1,207 @@
+//! A SQLite-backed index over on-disk snapshots and cached package tarball
+//! versions, mirroring the [`crate::snapshot`] API but backed by indexed
+//! queries instead of re-parsing every JSON file on disk.
+//!
+//! The JSON snapshot files remain the source of truth; this index is a
+//! rebuildable cache over them. [`Index::rebuild`] is only meant for the
+//! first time an index is created (or recovery from a missing/corrupt one) —
+//! day to day, [`Index::save`] and [`Index::delete_snapshot`] keep the
+//! snapshot tables in sync incrementally, and [`Index::refresh_cache_versions`]
+//! keeps the cheap-to-recompute cache-version table current, so that
+//! `clean`/`restore` never have to re-scan and re-deserialize every snapshot
+//! JSON file just to answer a query.
+
+use crate::snapshot::{self, Snapshot};
+use rusqlite::{params, Connection, Transaction};
+use std::path::{Path, PathBuf};
+
+/// A SQLite-index-related error.
+pub enum Error {
+    /// Some IO action failed.
+    Io(std::io::Error),
+    /// A SQLite operation failed.
+    Sqlite(rusqlite::Error),
+}
+
+impl From<std::io::Error> for Error {
+    fn from(v: std::io::Error) -> Self {
+        Self::Io(v)
+    }
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(v: rusqlite::Error) -> Self {
+        Self::Sqlite(v)
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Sqlite(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/// A handle to the SQLite index of snapshots and cached package versions.
+pub struct Index {
+    conn: Connection,
+}
+
+impl Index {
+    /// Open (creating if necessary) the index database at `path`.
+    pub fn open(path: &Path) -> Result<Index, Error> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS snapshots (
+                 path   TEXT PRIMARY KEY,
+                 time   TEXT NOT NULL,
+                 pinned INTEGER NOT NULL
+             );
+             CREATE TABLE IF NOT EXISTS snapshot_packages (
+                 snapshot TEXT NOT NULL REFERENCES snapshots(path),
+                 name     TEXT NOT NULL,
+                 version  TEXT NOT NULL
+             );
+             CREATE INDEX IF NOT EXISTS snapshot_packages_snapshot
+                 ON snapshot_packages(snapshot);
+             CREATE TABLE IF NOT EXISTS cache_versions (
+                 name    TEXT NOT NULL,
+                 version TEXT NOT NULL,
+                 PRIMARY KEY (name, version)
+             );",
+        )?;
+
+        Ok(Index { conn })
+    }
+
+    /// Wipe and fully repopulate the index from the on-disk JSON snapshots
+    /// found under `snapshot_dir`, plus the given cache tarball versions.
+    pub fn rebuild(
+        &mut self,
+        snapshot_dir: &Path,
+        cache_versions: &[(String, String)],
+    ) -> Result<(), Error> {
+        let tx = self.conn.transaction()?;
+        tx.execute("DELETE FROM snapshot_packages", [])?;
+        tx.execute("DELETE FROM snapshots", [])?;
+        tx.execute("DELETE FROM cache_versions", [])?;
+
+        for (path, snap) in snapshot::snapshots_with_paths(snapshot_dir) {
+            insert_snapshot(&tx, &path, &snap)?;
+        }
+
+        for (name, version) in cache_versions {
+            tx.execute(
+                "INSERT OR IGNORE INTO cache_versions (name, version) VALUES (?1, ?2)",
+                params![name, version],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Record a newly-saved snapshot, transactionally.
+    pub fn save(&mut self, path: &Path, snap: &Snapshot) -> Result<(), Error> {
+        let tx = self.conn.transaction()?;
+        insert_snapshot(&tx, path, snap)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Remove a snapshot that was deleted from disk, so the index doesn't go
+    /// stale the next time it's queried.
+    pub fn delete_snapshot(&mut self, path: &Path) -> Result<(), Error> {
+        let path_s = path.to_string_lossy();
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "DELETE FROM snapshot_packages WHERE snapshot = ?1",
+            params![path_s],
+        )?;
+        tx.execute("DELETE FROM snapshots WHERE path = ?1", params![path_s])?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Replace the indexed cache tarball versions with `cache_versions`,
+    /// without touching the (much more expensive to rebuild) snapshot
+    /// tables.
+    pub fn refresh_cache_versions(&mut self, cache_versions: &[(String, String)]) -> Result<(), Error> {
+        let tx = self.conn.transaction()?;
+        tx.execute("DELETE FROM cache_versions", [])?;
+        for (name, version) in cache_versions {
+            tx.execute(
+                "INSERT OR IGNORE INTO cache_versions (name, version) VALUES (?1, ?2)",
+                params![name, version],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Every indexed snapshot path paired with whether it's pinned, without
+    /// touching the JSON files themselves.
+    pub fn all_paths_pinned(&self) -> Result<Vec<(PathBuf, bool)>, Error> {
+        let mut stmt = self.conn.prepare("SELECT path, pinned FROM snapshots")?;
+        let rows = stmt
+            .query_map([], |row| {
+                let path: String = row.get(0)?;
+                let pinned: bool = row.get(1)?;
+                Ok((PathBuf::from(path), pinned))
+            })?
+            .filter_map(Result::ok)
+            .collect();
+
+        Ok(rows)
+    }
+
+    /// The paths of every indexed snapshot that is fully restorable given the
+    /// package versions currently present in the cache, i.e. every package
+    /// the snapshot names still has a matching tarball on disk.
+    pub fn restorable(&self) -> Result<Vec<PathBuf>, Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT s.path FROM snapshots s
+             WHERE NOT EXISTS (
+                 SELECT 1 FROM snapshot_packages sp
+                 WHERE sp.snapshot = s.path
+                 AND NOT EXISTS (
+                     SELECT 1 FROM cache_versions cv
+                     WHERE cv.name = sp.name AND cv.version = sp.version
+                 )
+             )",
+        )?;
+
+        let paths = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .filter_map(Result::ok)
+            .map(PathBuf::from)
+            .collect();
+
+        Ok(paths)
+    }
+}
+
+fn insert_snapshot(tx: &Transaction, path: &Path, snap: &Snapshot) -> Result<(), Error> {
+    let path_s = path.to_string_lossy();
+    let time = snap.time.format("%Y.%m(%b).%d.%H.%M.%S").to_string();
+
+    tx.execute(
+        "INSERT OR REPLACE INTO snapshots (path, time, pinned) VALUES (?1, ?2, ?3)",
+        params![path_s, time, snap.pinned],
+    )?;
+    tx.execute(
+        "DELETE FROM snapshot_packages WHERE snapshot = ?1",
+        params![path_s],
+    )?;
+
+    for (name, version) in snap.packages.iter() {
+        tx.execute(
+            "INSERT INTO snapshot_packages (snapshot, name, version) VALUES (?1, ?2, ?3)",
+            params![path_s, name, version],
+        )?;
+    }
+
+    Ok(())
+}
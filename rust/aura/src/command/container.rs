@@ -0,0 +1,82 @@
+//! Building AUR packages in throwaway containers instead of directly on the
+//! host, so that `base-devel`/makedepends never pollute the user's system.
+//!
+//! This is the frontend side of [`aura_core::container`]: it clones the
+//! package's AUR repo, runs the containerized build, and installs the
+//! tarballs it produced via `pacman -U`, exactly like the `-B` restore flow
+//! does.
+
+use crate::green;
+use aura_core::container::Runtime;
+use i18n_embed::fluent::FluentLanguageLoader;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+pub enum Error {
+    Io(std::io::Error),
+    Pacman(crate::pacman::Error),
+    Git(aura_core::git::Error),
+    Container(aura_core::container::Error),
+}
+
+impl From<std::io::Error> for Error {
+    fn from(v: std::io::Error) -> Self {
+        Self::Io(v)
+    }
+}
+
+impl From<crate::pacman::Error> for Error {
+    fn from(v: crate::pacman::Error) -> Self {
+        Self::Pacman(v)
+    }
+}
+
+impl From<aura_core::git::Error> for Error {
+    fn from(v: aura_core::git::Error) -> Self {
+        Self::Git(v)
+    }
+}
+
+impl From<aura_core::container::Error> for Error {
+    fn from(v: aura_core::container::Error) -> Self {
+        Self::Container(v)
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Pacman(e) => write!(f, "{}", e),
+            Error::Git(e) => write!(f, "{}", e),
+            Error::Container(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/// Clone an AUR package, build it inside a throwaway container, and install
+/// the resulting tarballs via `pacman -U`.
+pub(crate) fn build(
+    fll: &FluentLanguageLoader,
+    runtime: Runtime,
+    dockerfile: &str,
+    image: &str,
+    clone_dir: &Path,
+    pkg: &str,
+    flags: &str,
+    out_dir: &Path,
+) -> Result<(), Error> {
+    let url = PathBuf::from(format!("https://aur.archlinux.org/{}.git", pkg));
+    let pkg_dir = clone_dir.join(pkg);
+    aura_core::git::shallow_clone(&url, &pkg_dir)?;
+
+    // `out_dir` is Aura's shared package cache, not a scratch directory, so
+    // only the tarballs this specific build produced are installed.
+    let produced = aura_core::container::build(runtime, dockerfile, image, &pkg_dir, flags, out_dir)?;
+    let tarballs = produced.into_iter().map(PathBuf::into_os_string);
+
+    crate::pacman::sudo_pacman(std::iter::once(OsStr::new("-U").to_os_string()).chain(tarballs))?;
+
+    green!(fll, "common-done");
+    Ok(())
+}
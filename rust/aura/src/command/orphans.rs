@@ -1,5 +1,6 @@
 //! All functionality involving the `-O` command.
 
+use crate::utils::Answer;
 use crate::{a, green, yellow};
 use alpm::{Alpm, PackageReason, TransFlag};
 use aura_arch as arch;
@@ -13,6 +14,7 @@ pub enum Error {
     Alpm(alpm::Error),
     Readline(rustyline::error::ReadlineError),
     Sudo(crate::utils::SudoError),
+    Pacnew(crate::command::pacnew::Error),
     Cancelled,
     NoneExist,
 }
@@ -23,6 +25,12 @@ impl From<crate::utils::SudoError> for Error {
     }
 }
 
+impl From<crate::command::pacnew::Error> for Error {
+    fn from(v: crate::command::pacnew::Error) -> Self {
+        Self::Pacnew(v)
+    }
+}
+
 impl From<rustyline::error::ReadlineError> for Error {
     fn from(v: rustyline::error::ReadlineError) -> Self {
         Self::Readline(v)
@@ -41,6 +49,7 @@ impl std::fmt::Display for Error {
             Error::Alpm(e) => write!(f, "{}", e),
             Error::Readline(e) => write!(f, "{}", e),
             Error::Sudo(e) => write!(f, "{}", e),
+            Error::Pacnew(e) => write!(f, "{}", e),
             Error::NoneExist => write!(f, "No such packages exist."),
             Error::Cancelled => write!(f, "Action cancelled."),
         }
@@ -83,21 +92,47 @@ pub(crate) fn adopt(
 ///
 /// Will fail if the process does not have permission to create the lockfile,
 /// which usually lives in a root-owned directory.
-pub(crate) fn remove(alpm: &mut Alpm, fll: FluentLanguageLoader) -> Result<(), Error> {
+///
+/// The orphans are listed by number and only those the user picks are
+/// removed; picking none cancels the removal.
+///
+/// If `sudoloop` is set, a background thread refreshes the sudo timestamp for
+/// the duration of the transaction so that the removal isn't interrupted by a
+/// re-prompt (see `--sudoloop`).
+pub(crate) fn remove(alpm: &mut Alpm, fll: FluentLanguageLoader, sudoloop: bool) -> Result<(), Error> {
     crate::utils::sudo()?;
+    let _guard = sudoloop.then(crate::utils::sudo_loop);
 
     // Check for orphans.
     let orphans: Vec<_> = arch::orphans(alpm).collect();
     if !orphans.is_empty() {
-        // Copy the name of each original orphan.
-        let names: HashSet<_> = orphans.iter().map(|p| p.name().to_string()).collect();
+        // Let the user deselect specific orphans instead of removing all of
+        // them outright.
+        let orphan_names: Vec<_> = orphans.iter().map(|p| p.name().to_string()).collect();
+        let picks: HashSet<usize> = crate::utils::multi_select(">>> ", &orphan_names)?
+            .into_iter()
+            .collect();
+
+        let chosen: Vec<_> = orphans
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| picks.contains(i))
+            .map(|(_, p)| p)
+            .collect();
+
+        if chosen.is_empty() {
+            return Err(Error::Cancelled);
+        }
+
+        // Copy the name of each chosen orphan.
+        let names: HashSet<_> = chosen.iter().map(|p| p.name().to_string()).collect();
 
         // Initialize the transaction.
         let mut flag = TransFlag::RECURSE;
         flag.insert(TransFlag::UNNEEDED);
         alpm.trans_init(flag)?;
 
-        for p in orphans {
+        for p in chosen {
             alpm.trans_remove_pkg(p)?;
         }
 
@@ -125,10 +160,12 @@ pub(crate) fn remove(alpm: &mut Alpm, fll: FluentLanguageLoader) -> Result<(), E
 
         // Proceed with the removal if the user accepts.
         let msg = format!("{} {} ", fl!(fll, "proceed"), fl!(fll, "proceed-yes"));
-        crate::utils::prompt(&a!(msg)).ok_or(Error::Cancelled)?;
+        crate::utils::prompt(&fll, &a!(msg), Answer::Yes).ok_or(Error::Cancelled)?;
         alpm.trans_commit().map_err(|(_, e)| Error::Alpm(e))?;
         alpm.trans_release()?;
         green!(fll, "common-done");
+
+        crate::command::pacnew::review(&fll, std::path::Path::new("/etc"))?;
     }
 
     Ok(())
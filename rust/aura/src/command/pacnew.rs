@@ -0,0 +1,68 @@
+//! Reviewing `.pacnew`/`.pacsave` files left behind after a transaction.
+
+use crate::utils::Answer;
+use crate::{a, yellow};
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use log::warn;
+use std::env;
+use std::path::Path;
+use std::process::Command;
+
+pub enum Error {
+    Readline(rustyline::error::ReadlineError),
+}
+
+impl From<rustyline::error::ReadlineError> for Error {
+    fn from(v: rustyline::error::ReadlineError) -> Self {
+        Self::Readline(v)
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Readline(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/// The merge tool to fall back on when `$DIFFPROG` isn't set.
+const DEFAULT_DIFFPROG: &str = "vimdiff";
+
+/// Scan `root` for `.pacnew`/`.pacsave` files and, behind a confirmation
+/// prompt, offer to merge each one with its original via `$DIFFPROG`.
+///
+/// Safe by default: declining the prompt leaves every file untouched. Called
+/// after a transaction that might have left these behind, from
+/// `orphans::remove` and `snapshot::restore`. It does not escalate privileges
+/// itself: `orphans::remove` calls `utils::sudo()` beforehand, while
+/// `snapshot::restore` does not.
+pub(crate) fn review(fll: &FluentLanguageLoader, root: &Path) -> Result<(), Error> {
+    let found = aura_core::pacnew::scan(root);
+    if found.is_empty() {
+        return Ok(());
+    }
+
+    yellow!(fll, "pacnew-found", count = found.len());
+    for pf in &found {
+        println!("  {}", pf.new.display());
+    }
+
+    let msg = format!("{} {} ", fl!(fll, "pacnew-merge"), fl!(fll, "proceed-no"));
+    if crate::utils::prompt(fll, &a!(msg), Answer::No).is_none() {
+        return Ok(());
+    }
+
+    let diffprog = env::var("DIFFPROG").unwrap_or_else(|_| DEFAULT_DIFFPROG.to_string());
+
+    for pf in found {
+        match Command::new(&diffprog).arg(&pf.original).arg(&pf.new).status() {
+            Ok(status) if status.success() => {}
+            Ok(status) => warn!("{} exited with {} for {}", diffprog, status, pf.new.display()),
+            Err(e) => warn!("Failed to launch {}: {}", diffprog, e),
+        }
+    }
+
+    Ok(())
+}
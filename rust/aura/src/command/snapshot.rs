@@ -1,5 +1,6 @@
 //! All functionality involving the `-B` command.
 
+use crate::utils::Answer;
 use crate::{a, aura, green, red};
 use alpm::Alpm;
 use aura_core::snapshot::Snapshot;
@@ -10,7 +11,7 @@ use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::BufWriter;
 use std::ops::Not;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::{cmp::Ordering, ffi::OsStr};
 
 pub enum Error {
@@ -19,16 +20,30 @@ pub enum Error {
     Pacman(crate::pacman::Error),
     Readline(rustyline::error::ReadlineError),
     Json(serde_json::Error),
+    Pacnew(crate::command::pacnew::Error),
+    Index(aura_core::snapshot_db::Error),
     Cancelled,
     Silent,
 }
 
+impl From<aura_core::snapshot_db::Error> for Error {
+    fn from(v: aura_core::snapshot_db::Error) -> Self {
+        Self::Index(v)
+    }
+}
+
 impl From<crate::pacman::Error> for Error {
     fn from(v: crate::pacman::Error) -> Self {
         Self::Pacman(v)
     }
 }
 
+impl From<crate::command::pacnew::Error> for Error {
+    fn from(v: crate::command::pacnew::Error) -> Self {
+        Self::Pacnew(v)
+    }
+}
+
 impl From<serde_json::Error> for Error {
     fn from(v: serde_json::Error) -> Self {
         Self::Json(v)
@@ -61,6 +76,8 @@ impl std::fmt::Display for Error {
             Error::Pacman(e) => write!(f, "{}", e),
             Error::Readline(e) => write!(f, "{}", e),
             Error::Json(e) => write!(f, "{}", e),
+            Error::Pacnew(e) => write!(f, "{}", e),
+            Error::Index(e) => write!(f, "{}", e),
             Error::Silent => write!(f, ""),
             Error::Cancelled => write!(f, "Action cancelled."),
         }
@@ -79,29 +96,49 @@ struct StateDiff<'a> {
 }
 
 pub(crate) fn save(fll: &FluentLanguageLoader, alpm: &Alpm) -> Result<(), Error> {
-    let mut cache = crate::dirs::snapshot()?;
+    let snapshot_dir = crate::dirs::snapshot()?;
     let snap = Snapshot::from_alpm(alpm);
     let name = format!("{}.json", snap.time.format("%Y.%m(%b).%d.%H.%M.%S"));
-    cache.push(name);
+    let path = snapshot_dir.join(name);
 
-    let file = BufWriter::new(File::create(cache)?);
+    let file = BufWriter::new(File::create(&path)?);
     serde_json::to_writer(file, &snap)?;
+
+    // Keep the index in sync as new snapshots are taken, rather than forcing
+    // the next `clean`/`restore` to rebuild it from scratch.
+    let db_path = index_db_path(&snapshot_dir);
+    let existed = db_path.exists();
+    let mut db = aura_core::snapshot_db::Index::open(&db_path)?;
+    if existed {
+        db.save(&path, &snap)?;
+    } else {
+        // A brand new index is populated from every JSON file on disk (this
+        // one included), not just the snapshot being saved.
+        db.rebuild(&snapshot_dir, &[])?;
+    }
+
     green!(fll, "B-saved");
 
     Ok(())
 }
 
-/// Remove all saveds snapshots that don't have tarballs in the cache.
+/// Remove all saved snapshots that don't have tarballs in the cache.
+///
+/// Consults the SQLite index (see [`aura_core::snapshot_db`]) instead of
+/// deserializing every snapshot JSON file to decide what's usable.
 pub(crate) fn clean(fll: &FluentLanguageLoader, caches: &[&Path]) -> Result<(), Error> {
     let msg = format!("{} {} ", fl!(fll, "B-clean"), fl!(fll, "proceed-yes"));
-    crate::utils::prompt(&a!(msg)).ok_or(Error::Cancelled)?;
+    crate::utils::prompt(fll, &a!(msg), Answer::Yes).ok_or(Error::Cancelled)?;
 
-    let path = crate::dirs::snapshot()?;
+    let snapshot_dir = crate::dirs::snapshot()?;
     let vers = aura_core::cache::all_versions(caches);
+    let mut db = open_index(&snapshot_dir, &vers)?;
 
-    for (path, snapshot) in aura_core::snapshot::snapshots_with_paths(&path) {
-        if snapshot.pinned.not() && snapshot.usable(&vers).not() {
-            std::fs::remove_file(path)?;
+    let keep: HashSet<PathBuf> = db.restorable()?.into_iter().collect();
+    for (path, pinned) in db.all_paths_pinned()? {
+        if pinned.not() && keep.contains(&path).not() {
+            std::fs::remove_file(&path)?;
+            db.delete_snapshot(&path)?;
         }
     }
 
@@ -124,14 +161,20 @@ pub(crate) fn restore(
     fll: &FluentLanguageLoader,
     alpm: &Alpm,
     caches: &[&Path],
+    sudoloop: bool,
 ) -> Result<(), Error> {
-    let snap = crate::dirs::snapshot()?;
+    let snapshot_dir = crate::dirs::snapshot()?;
     let vers = aura_core::cache::all_versions(caches);
-
-    let mut shots: Vec<_> = aura_core::snapshot::snapshots(&snap)
-        .filter(|ss| ss.usable(&vers))
+    let db = open_index(&snapshot_dir, &vers)?;
+
+    // Only the snapshots the index reports as restorable get deserialized,
+    // instead of every JSON file under `snapshot_dir`.
+    let mut shots: Vec<(PathBuf, Snapshot)> = db
+        .restorable()?
+        .into_iter()
+        .filter_map(|path| load_snapshot(&path).ok().map(|snap| (path, snap)))
         .collect();
-    shots.sort_by_key(|ss| ss.time);
+    shots.sort_by_key(|(_, ss)| ss.time);
     let digits = 1 + (shots.len() / 10);
 
     if shots.is_empty() {
@@ -140,27 +183,113 @@ pub(crate) fn restore(
     }
 
     aura!(fll, "B-select");
-    for (i, ss) in shots.iter().enumerate() {
+    for (i, (_, ss)) in shots.iter().enumerate() {
         let time = ss.time.format("%Y %B %d %T");
         let pinned = ss.pinned.then(|| "[pinned]".cyan()).unwrap_or_default();
         println!(" {:w$}) {} {}", i, time, pinned, w = digits);
     }
 
-    let index = crate::utils::select(">>> ", shots.len() - 1)?;
-    restore_snapshot(alpm, caches, shots.remove(index))?;
+    let choice = crate::utils::select(">>> ", shots.len() - 1)?;
+    let (_, chosen) = shots.remove(choice);
+    restore_snapshot(alpm, caches, chosen, sudoloop)?;
 
     green!(fll, "common-done");
+
+    crate::command::pacnew::review(fll, Path::new("/etc"))?;
     Ok(())
 }
 
-fn restore_snapshot(alpm: &Alpm, caches: &[&Path], snapshot: Snapshot) -> Result<(), Error> {
+/// Where the `-B` SQLite index lives, alongside the JSON snapshot files it
+/// indexes.
+fn index_db_path(snapshot_dir: &Path) -> PathBuf {
+    snapshot_dir.join("index.sqlite")
+}
+
+/// Open this snapshot directory's SQLite index, refreshing it against the
+/// given cache versions.
+///
+/// The snapshot tables themselves are *not* rescanned from disk here: `save`
+/// and `clean` already keep them in sync incrementally as snapshots are
+/// created/removed, so the only index that needs redoing from the (cheap)
+/// `vers` map each call is `cache_versions`. A full [`Index::rebuild`] only
+/// happens the first time this directory's index is created.
+fn open_index(
+    snapshot_dir: &Path,
+    vers: &HashMap<String, HashSet<String>>,
+) -> Result<aura_core::snapshot_db::Index, Error> {
+    let db_path = index_db_path(snapshot_dir);
+    let existed = db_path.exists();
+
+    let mut db = aura_core::snapshot_db::Index::open(&db_path)?;
+
+    let flat: Vec<(String, String)> = vers
+        .iter()
+        .flat_map(|(name, versions)| versions.iter().map(move |v| (name.clone(), v.clone())))
+        .collect();
+
+    if existed {
+        db.refresh_cache_versions(&flat)?;
+    } else {
+        db.rebuild(snapshot_dir, &flat)?;
+    }
+
+    Ok(db)
+}
+
+/// Deserialize a single snapshot JSON file.
+fn load_snapshot(path: &Path) -> Result<Snapshot, Error> {
+    let file = File::open(path)?;
+    Ok(serde_json::from_reader(file)?)
+}
+
+/// Restore a single snapshot's packages, bringing the system back to that
+/// point-in-time package state.
+///
+/// The needed installs/alterations and removals are listed by number, and
+/// only those the user picks are carried out.
+///
+/// If `sudoloop` is set, a background thread refreshes the sudo timestamp
+/// for the duration of the `pacman` calls below (see `--sudoloop`).
+fn restore_snapshot(
+    alpm: &Alpm,
+    caches: &[&Path],
+    snapshot: Snapshot,
+    sudoloop: bool,
+) -> Result<(), Error> {
+    let _guard = sudoloop.then(crate::utils::sudo_loop);
+
     let installed: HashMap<&str, &str> = alpm
         .localdb()
         .pkgs()
         .iter()
         .map(|p| (p.name(), p.version().as_str()))
         .collect();
-    let diff = package_diff(&snapshot, &installed);
+    let mut diff = package_diff(&snapshot, &installed);
+
+    // Let the user deselect specific packages instead of restoring
+    // everything the diff calls for.
+    let mut labels: Vec<String> = diff
+        .to_add_or_alter
+        .keys()
+        .map(|n| format!("install/alter {}", n))
+        .chain(diff.to_remove.iter().map(|n| format!("remove {}", n)))
+        .collect();
+    labels.sort();
+
+    if !labels.is_empty() {
+        let picks: HashSet<usize> = crate::utils::multi_select(">>> ", &labels)?
+            .into_iter()
+            .collect();
+        let chosen: HashSet<&str> = labels
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| picks.contains(i))
+            .filter_map(|(_, l)| l.rsplit(' ').next())
+            .collect();
+
+        diff.to_add_or_alter.retain(|n, _| chosen.contains(n));
+        diff.to_remove.retain(|n| chosen.contains(n));
+    }
 
     // Alter packages first to avoid potential breakage from the later removal
     // step.
@@ -0,0 +1,35 @@
+//! Batch `git` synchronization of AUR package repositories, so that syncing
+//! or building a large package set doesn't pay for each clone/pull's network
+//! round-trip serially.
+
+use aura_core::git;
+use log::warn;
+use std::path::{Path, PathBuf};
+
+/// Shallow-clone every package in `pkgs` into `clone_dir`, running up to
+/// [`git::DEFAULT_JOBS`] clones concurrently. A failed clone is logged and
+/// doesn't stop the rest of the batch.
+pub(crate) fn clone_all(clone_dir: &Path, pkgs: &[String]) -> git::BatchResult {
+    let repos: Vec<(PathBuf, PathBuf)> = pkgs
+        .iter()
+        .map(|p| {
+            let url = PathBuf::from(format!("https://aur.archlinux.org/{}.git", p));
+            (url, clone_dir.join(p))
+        })
+        .collect();
+
+    let result = git::clone_many(&repos, git::DEFAULT_JOBS);
+    for (path, e) in &result.failed {
+        warn!("Failed to clone into {}: {}", path.display(), e);
+    }
+    result
+}
+
+/// Pull every already-cloned repository in `dirs`. See [`clone_all`].
+pub(crate) fn pull_all(dirs: &[PathBuf]) -> git::BatchResult {
+    let result = git::pull_many(dirs, git::DEFAULT_JOBS);
+    for (path, e) in &result.failed {
+        warn!("Failed to pull {}: {}", path.display(), e);
+    }
+    result
+}
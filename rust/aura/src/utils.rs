@@ -1,11 +1,22 @@
 //! Various utility functions.
 
 use colored::{ColoredString, Colorize};
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use log::warn;
 use rustyline::Editor;
 use std::io::Write;
+use std::process::Command;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
 use unic_langid::LanguageIdentifier;
 
+/// How often to refresh the sudo timestamp while a [`SudoGuard`] is alive.
+const SUDO_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
 /// Injection of the `void` method into [`Result`], which is a common shorthand
 /// for "forgetting" the internal return value of a `Result`. Note that this
 /// also automatically lifts the Error type via [`From`], as it is intended as
@@ -64,13 +75,32 @@ fn pad(mult: usize, longest: usize, s: &str) -> usize {
     mult * (longest - s.chars().count())
 }
 
-// TODO Localize the acceptance chars.
-/// Prompt the user for confirmation.
-pub(crate) fn prompt(msg: &str) -> Option<()> {
+/// The default answer to a yes/no [`prompt`], used when the user just hits
+/// enter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Answer {
+    /// An empty line is taken to mean "yes".
+    Yes,
+    /// An empty line is taken to mean "no".
+    No,
+}
+
+/// Prompt the user for confirmation, falling back to `default` on an empty
+/// line. The accepted affirmative characters are pulled from the current
+/// locale (`utils-yes`) rather than being hard-coded to `y`/`Y`; any other
+/// non-empty answer is taken as "no", and no negative characters are looked
+/// up.
+pub(crate) fn prompt(fll: &FluentLanguageLoader, msg: &str, default: Answer) -> Option<()> {
     let mut rl = Editor::<()>::new();
     let line = rl.readline(msg).ok()?;
+    let line = line.trim();
 
-    (line.is_empty() || line == "y" || line == "Y").then(|| ())
+    if line.is_empty() {
+        return (default == Answer::Yes).then(|| ());
+    }
+
+    let yes = fl!(fll, "utils-yes");
+    yes.split('/').any(|c| c.eq_ignore_ascii_case(line)).then(|| ())
 }
 
 /// Prompt the user for a numerical selection.
@@ -88,6 +118,70 @@ pub(crate) fn select(msg: &str, max: usize) -> Result<usize, rustyline::error::R
     }
 }
 
+/// Prompt the user to select any number of items from a numbered list.
+///
+/// Accepts a line of space/comma-separated indices, with inclusive ranges
+/// (e.g. `1 3 5-8`). An empty line (or one naming no indices at all) selects
+/// nothing. If any token is out of range or fails to parse, the whole line
+/// is rejected and the user is re-prompted.
+pub(crate) fn multi_select<S: std::fmt::Display>(
+    msg: &str,
+    items: &[S],
+) -> Result<Vec<usize>, rustyline::error::ReadlineError> {
+    let mut rl = Editor::<()>::new();
+
+    for (i, item) in items.iter().enumerate() {
+        println!(" {}) {}", i, item);
+    }
+
+    loop {
+        let raw = rl.readline(msg)?;
+
+        if let Some(mut picks) = parse_picks(&raw, items.len()) {
+            picks.sort_unstable();
+            picks.dedup();
+            return Ok(picks);
+        }
+    }
+}
+
+/// Parse a line like `1 3 5-8` into the set of indices it names, rejecting
+/// the whole line if any token is malformed or falls outside `0..max`.
+///
+/// A line naming no indices at all (blank, or only separators) is a valid
+/// "select nothing", not a rejection — callers that need to distinguish
+/// "user picked zero items" from "still deciding" should treat the returned
+/// empty `Vec` as the former.
+fn parse_picks(raw: &str, max: usize) -> Option<Vec<usize>> {
+    let mut picks = Vec::new();
+
+    for token in raw.split(|c: char| c == ',' || c.is_whitespace()) {
+        if token.is_empty() {
+            continue;
+        }
+
+        match token.split_once('-') {
+            Some((lo, hi)) => {
+                let lo = usize::from_str(lo).ok()?;
+                let hi = usize::from_str(hi).ok()?;
+                if lo > hi || hi >= max {
+                    return None;
+                }
+                picks.extend(lo..=hi);
+            }
+            None => {
+                let n = usize::from_str(token).ok()?;
+                if n >= max {
+                    return None;
+                }
+                picks.push(n);
+            }
+        }
+    }
+
+    Some(picks)
+}
+
 pub struct SudoError;
 
 impl std::fmt::Display for SudoError {
@@ -100,3 +194,51 @@ impl std::fmt::Display for SudoError {
 pub(crate) fn sudo() -> Result<(), SudoError> {
     sudo::escalate_if_needed().map_err(|_| SudoError).void()
 }
+
+/// A drop-guard for the background `--sudoloop` thread spawned by
+/// [`sudo_loop`].
+///
+/// Dropping the guard stops the refresh loop, so it should be held for the
+/// full duration of whatever privileged transaction it was started for.
+pub(crate) struct SudoGuard {
+    alive: Arc<AtomicBool>,
+}
+
+impl Drop for SudoGuard {
+    fn drop(&mut self) {
+        self.alive.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Spawn a daemon thread that periodically runs `sudo -v` to refresh the
+/// cached timestamp, so that long privileged transactions (see `--sudoloop`;
+/// the `-O` removal and `-B` restore hold one) don't hit a re-prompt
+/// mid-transaction.
+///
+/// The loop runs until the returned [`SudoGuard`] is dropped. A failed
+/// refresh is only logged as a warning; it does not kill the process, since
+/// the original escalation may still be valid for a while longer.
+pub(crate) fn sudo_loop() -> SudoGuard {
+    let alive = Arc::new(AtomicBool::new(true));
+    let guard = SudoGuard {
+        alive: alive.clone(),
+    };
+
+    thread::spawn(move || {
+        while alive.load(Ordering::SeqCst) {
+            thread::sleep(SUDO_REFRESH_INTERVAL);
+
+            if !alive.load(Ordering::SeqCst) {
+                break;
+            }
+
+            match Command::new("sudo").arg("-v").status() {
+                Ok(status) if status.success() => {}
+                Ok(status) => warn!("`sudo -v` exited with {}", status),
+                Err(e) => warn!("Failed to refresh the sudo timestamp: {}", e),
+            }
+        }
+    });
+
+    guard
+}